@@ -0,0 +1,39 @@
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use subtle::ConstantTimeEq;
+
+use crate::{error::ApiError, store::PriceStore, AppState};
+
+/// Rejects mutating requests that don't carry the configured API key in
+/// their `Authorization` header. Applied only to the write routes; `GET`
+/// routes stay public.
+pub async fn require_api_key<S: PriceStore>(
+    State(state): State<AppState<S>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| api_key_matches(value, &state.api_key));
+
+    if authorized {
+        Ok(next.run(request).await)
+    } else {
+        Err(ApiError::Unauthorized)
+    }
+}
+
+/// Compares in constant time so a caller probing the endpoint can't learn
+/// how many leading bytes of the key they guessed correctly.
+fn api_key_matches(provided: &str, expected: &str) -> bool {
+    let provided = provided.as_bytes();
+    let expected = expected.as_bytes();
+
+    provided.len() == expected.len() && bool::from(provided.ct_eq(expected))
+}
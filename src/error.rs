@@ -0,0 +1,60 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+/// Failure modes the API can report to a client as a structured JSON body.
+#[derive(Debug, Clone, Copy)]
+pub enum ApiError {
+    PriceNotFound,
+    StorageUnavailable,
+    Unauthorized,
+}
+
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        match self {
+            ApiError::PriceNotFound => StatusCode::NOT_FOUND,
+            ApiError::StorageUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            ApiError::PriceNotFound => "price_not_found",
+            ApiError::StorageUnavailable => "storage_unavailable",
+            ApiError::Unauthorized => "unauthorized",
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            ApiError::PriceNotFound => "no price was found for the given id",
+            ApiError::StorageUnavailable => "the price store is currently unavailable",
+            ApiError::Unauthorized => "a valid API key is required for this request",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ApiErrorBody {
+    code: &'static str,
+    message: &'static str,
+    status: u16,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body = ApiErrorBody {
+            code: self.code(),
+            message: self.message(),
+            status: status.as_u16(),
+        };
+
+        (status, Json(body)).into_response()
+    }
+}
@@ -1,24 +1,59 @@
 use std::{
-    collections::HashMap,
+    convert::Infallible,
     sync::Arc,
+    time::Duration,
 };
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
-    response::IntoResponse,
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
     Router,
     routing::*,
 };
+use futures::Stream;
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
 use uuid::Uuid;
 
+mod auth;
+mod error;
+mod queue;
+mod store;
+
+use error::ApiError;
+use queue::WriteQueue;
+use store::{MemoryStore, PriceStore, SledStore};
+
+const WRITE_QUEUE_CAPACITY: usize = 256;
+
 #[tokio::main]
 async fn main() {
-    let prices = Arc::new(RwLock::new(HashMap::default()));
-    let app = app(prices);
+    let (events, _) = broadcast::channel(100);
+    let api_key: Arc<str> = Arc::from(std::env::var("API_KEY").expect("API_KEY must be set"));
+
+    match std::env::var("PRICE_STORE_PATH") {
+        Ok(path) => {
+            let store = Arc::new(SledStore::open(path).expect("failed to open sled store"));
+            let queue = WriteQueue::spawn(store.clone(), events.clone(), WRITE_QUEUE_CAPACITY);
+            serve(AppState { store, events, queue, api_key }).await;
+        }
+        Err(_) => {
+            let store = Arc::new(MemoryStore::default());
+            let queue = WriteQueue::spawn(store.clone(), events.clone(), WRITE_QUEUE_CAPACITY);
+            serve(AppState { store, events, queue, api_key }).await;
+        }
+    }
+}
+
+async fn serve<S: PriceStore + 'static>(state: AppState<S>) {
+    let app = app(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:3001")
         .await
@@ -27,37 +62,137 @@ async fn main() {
     axum::serve(listener, app).await.unwrap();
 }
 
-fn app(state: TPriceMap) -> Router {
-    Router::new()
-        .route("/prices", get(get_prices).post(create_price))
-        .route("/prices/:id", get(get_price_by_id).patch(update_price_by_id).delete(delete_price))
-        .with_state(state)
+fn app<S: PriceStore + 'static>(state: AppState<S>) -> Router {
+    let public = Router::new()
+        .route("/prices", get(get_prices::<S>))
+        .route("/prices/events", get(price_events::<S>))
+        .route("/prices/:id", get(get_price_by_id::<S>))
+        .route("/queue/depth", get(queue_depth::<S>));
+
+    let protected = Router::new()
+        .route("/prices", post(create_price::<S>))
+        .route("/prices/:id", patch(update_price_by_id::<S>).delete(delete_price::<S>))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_api_key::<S>));
+
+    public.merge(protected).with_state(state)
+}
+
+pub(crate) struct AppState<S: PriceStore> {
+    store: Arc<S>,
+    events: broadcast::Sender<PriceEvent>,
+    queue: WriteQueue,
+    pub(crate) api_key: Arc<str>,
+}
+
+impl<S: PriceStore> Clone for AppState<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            events: self.events.clone(),
+            queue: self.queue.clone(),
+            api_key: self.api_key.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum PriceEvent {
+    Created { id: Uuid, price: TPrice },
+    Updated { id: Uuid, price: TPrice },
+    Deleted { id: Uuid },
+}
+
+#[derive(Debug, Deserialize)]
+struct ListParams {
+    limit: Option<usize>,
+    offset: Option<usize>,
+    min: Option<TPrice>,
+    max: Option<TPrice>,
+    sort: Option<SortOrder>,
 }
 
-async fn get_prices(
-    State(prices): State<TPriceMap>,
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Serialize)]
+struct PriceList {
+    items: Vec<TPrice>,
+    total: usize,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+async fn get_prices<S: PriceStore>(
+    State(state): State<AppState<S>>,
+    Query(params): Query<ListParams>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let prices = prices.read().await;
-    Ok(Json(prices.values().cloned().collect::<Vec<TPrice>>()))
+    let mut prices = state.store.list().await;
+
+    if let Some(min) = params.min {
+        prices.retain(|price| *price >= min);
+    }
+    if let Some(max) = params.max {
+        prices.retain(|price| *price <= max);
+    }
+
+    match params.sort {
+        Some(SortOrder::Asc) => prices.sort_unstable(),
+        Some(SortOrder::Desc) => prices.sort_unstable_by(|a, b| b.cmp(a)),
+        None => {}
+    }
+
+    let total = prices.len();
+    let offset = params.offset.unwrap_or(0);
+    let items = match params.limit {
+        Some(limit) => prices.into_iter().skip(offset).take(limit).collect(),
+        None => prices.into_iter().skip(offset).collect(),
+    };
+
+    Ok(Json(PriceList { items, total, limit: params.limit, offset }))
+}
+
+async fn price_events<S: PriceStore>(
+    State(state): State<AppState<S>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(state.events.subscribe())
+        .filter_map(|event| event.ok())
+        .map(|event| Ok(Event::default().json_data(&event).unwrap()));
+
+    Sse::new(stream).keep_alive(KeepAlive::new().interval(Duration::from_secs(15)))
+}
+
+async fn queue_depth<S: PriceStore>(
+    State(state): State<AppState<S>>,
+) -> impl IntoResponse {
+    state.queue.depth().to_string()
 }
 
-async fn create_price(
-    State(prices): State<TPriceMap>,
+async fn create_price<S: PriceStore>(
+    State(state): State<AppState<S>>,
     Json(input): Json<PriceDto>,
-) -> Result<impl IntoResponse, StatusCode> {
+) -> Result<impl IntoResponse, ApiError> {
     let uuid = Uuid::new_v4();
-    prices.write().await.insert(uuid, input.price);
+    state
+        .queue
+        .create(uuid, input.price)
+        .await
+        .map_err(|_| ApiError::StorageUnavailable)?;
 
     Ok(uuid.to_string())
 }
 
-async fn get_price_by_id(
+async fn get_price_by_id<S: PriceStore>(
     Path(id): Path<Uuid>,
-    State(prices): State<TPriceMap>,
-) -> Result<impl IntoResponse, StatusCode> {
-    match prices.read().await.get(&id) {
+    State(state): State<AppState<S>>,
+) -> Result<impl IntoResponse, ApiError> {
+    match state.store.get(id).await {
         Some(price) => Ok(price.to_string()),
-        None => Err(StatusCode::NOT_FOUND)
+        None => Err(ApiError::PriceNotFound)
     }
 }
 
@@ -66,35 +201,35 @@ struct PriceDto {
     price: TPrice,
 }
 
-async fn update_price_by_id(
+async fn update_price_by_id<S: PriceStore>(
     Path(id): Path<Uuid>,
-    State(prices): State<TPriceMap>,
+    State(state): State<AppState<S>>,
     Json(input): Json<PriceDto>,
-) -> Result<impl IntoResponse, StatusCode> {
-    match prices.write().await.get_mut(&id) {
-        Some(old_price) => {
-            *old_price = input.price;
-            Ok(StatusCode::OK)
-        },
-        None => Err(StatusCode::NOT_FOUND)
+) -> Result<impl IntoResponse, ApiError> {
+    match state.queue.update(id, input.price).await {
+        Ok(true) => Ok(StatusCode::OK),
+        Ok(false) => Err(ApiError::PriceNotFound),
+        Err(_) => Err(ApiError::StorageUnavailable),
     }
 }
 
-async fn delete_price(
+async fn delete_price<S: PriceStore>(
     Path(id): Path<Uuid>,
-    State(prices): State<TPriceMap>,
-) -> Result<impl IntoResponse, StatusCode> {
-    match prices.write().await.remove_entry(&id) {
-        Some(_) => Ok(StatusCode::OK),
-        None => Err(StatusCode::NOT_FOUND)
+    State(state): State<AppState<S>>,
+) -> Result<impl IntoResponse, ApiError> {
+    match state.queue.delete(id).await {
+        Ok(true) => Ok(StatusCode::OK),
+        Ok(false) => Err(ApiError::PriceNotFound),
+        Err(_) => Err(ApiError::StorageUnavailable),
     }
 }
 
-type TPrice = u64;
-type TPriceMap = Arc<RwLock<HashMap<Uuid, TPrice>>>;
+pub(crate) type TPrice = u64;
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use axum::{
         body::Body,
         http::{self, Request, StatusCode},
@@ -108,12 +243,14 @@ mod tests {
 
     use super::*;
 
+    const TEST_API_KEY: &str = "test-api-key";
+
     #[tokio::test]
     async fn get_prices_test() {
         let uuid = Uuid::new_v4();
         let map_with_entry = build_test_hashmap_with_entry(uuid, 355);
-        
-        let state = Arc::new(RwLock::new(map_with_entry));
+
+        let state = build_test_state(map_with_entry);
         let mut app = app(state).into_service();
 
         let request = build_request(
@@ -125,14 +262,69 @@ mod tests {
         let response = call(request, &mut app).await;
 
         assert_eq!(response.status(), StatusCode::OK);
-        assert_eq!(collect_body(response).await, "[355]");
+        assert_eq!(
+            collect_body(response).await,
+            r#"{"items":[355],"total":1,"limit":null,"offset":0}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn get_prices_pagination_and_range_test() {
+        let mut prices = HashMap::new();
+        for price in [100, 200, 300, 400] {
+            prices.insert(Uuid::new_v4(), price);
+        }
+
+        let state = build_test_state(prices);
+        let mut app = app(state).into_service();
+
+        let request = build_request(
+            http::Method::GET,
+            "/prices?min=200&max=400&sort=desc&limit=2&offset=1",
+            None
+        );
+
+        let response = call(request, &mut app).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            collect_body(response).await,
+            r#"{"items":[300,200],"total":3,"limit":2,"offset":1}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn price_events_stream_emits_created_event_test() {
+        let state = build_test_state(HashMap::new());
+        let events = state.events.clone();
+        let mut app = app(state).into_service();
+
+        let request = build_request(http::Method::GET, "/prices/events", None);
+        let response = call(request, &mut app).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let id = Uuid::new_v4();
+        events.send(PriceEvent::Created { id, price: 355 }).unwrap();
+
+        let mut body = response.into_body();
+        let frame = tokio::time::timeout(Duration::from_secs(1), body.frame())
+            .await
+            .expect("timed out waiting for an SSE frame")
+            .expect("stream ended unexpectedly")
+            .expect("frame error");
+        let chunk = frame.into_data().unwrap();
+        let text = String::from_utf8(chunk.to_vec()).unwrap();
+
+        assert!(text.contains("\"type\":\"created\""));
+        assert!(text.contains(&format!("\"id\":\"{id}\"")));
+        assert!(text.contains("\"price\":355"));
     }
-    
+
     #[tokio::test]
     async fn get_price_by_id_test() {
         let uuid = Uuid::new_v4();
         let map_with_entry = build_test_hashmap_with_entry(uuid, 355);
-        let state = Arc::new(RwLock::new(map_with_entry));
+        let state = build_test_state(map_with_entry);
         let mut app = app(state).into_service();
 
         let request = build_request(
@@ -149,7 +341,7 @@ mod tests {
 
     #[tokio::test]
     async fn get_not_found_price_by_id_test() {
-        let state = Arc::new(RwLock::new(HashMap::new()));
+        let state = build_test_state(HashMap::new());
         let mut app = app(state).into_service();
 
         let request = build_request(
@@ -159,17 +351,20 @@ mod tests {
         );
         let response = call(request, &mut app).await;
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
-        assert_eq!(collect_body(response).await, "");
+        assert_eq!(
+            collect_body(response).await,
+            r#"{"code":"price_not_found","message":"no price was found for the given id","status":404}"#
+        );
     }
 
     #[tokio::test]
     async fn patch_price_by_id_test() {
         let uuid = Uuid::new_v4();
         let map_with_entry = build_test_hashmap_with_entry(uuid, 355);
-        let state = Arc::new(RwLock::new(map_with_entry));
+        let state = build_test_state(map_with_entry);
         let mut app = app(state).into_service();
 
-        let request = build_request(
+        let request = build_authenticated_request(
             http::Method::PATCH,
             &format!("/prices/{}", uuid),
             Some(&json!({"price": 235}))
@@ -191,10 +386,10 @@ mod tests {
     async fn delete_price_test() {
         let uuid = Uuid::new_v4();
         let map_with_entry = build_test_hashmap_with_entry(uuid, 355);
-        let state = Arc::new(RwLock::new(map_with_entry));
+        let state = build_test_state(map_with_entry);
         let mut app = app(state).into_service();
 
-        let request = build_request(
+        let request = build_authenticated_request(
             http::Method::DELETE,
             &format!("/prices/{}", uuid),
             None
@@ -209,7 +404,24 @@ mod tests {
         );
         let response = call(request, &mut app).await;
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
-        assert_eq!(collect_body(response).await, "");
+        assert_eq!(
+            collect_body(response).await,
+            r#"{"code":"price_not_found","message":"no price was found for the given id","status":404}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn create_price_without_api_key_is_rejected_test() {
+        let state = build_test_state(HashMap::new());
+        let mut app = app(state).into_service();
+
+        let request = build_request(
+            http::Method::POST,
+            "/prices",
+            Some(&json!({"price": 355}))
+        );
+        let response = call(request, &mut app).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
     fn build_test_hashmap_with_entry(uuid: Uuid, value: TPrice) -> HashMap<Uuid, TPrice> {
@@ -219,6 +431,18 @@ mod tests {
         hashmap_with_test_price_entry
     }
 
+    fn build_test_state(map: HashMap<Uuid, TPrice>) -> AppState<MemoryStore> {
+        let (events, _) = broadcast::channel(100);
+        let store = Arc::new(MemoryStore::from_map(map));
+        let queue = WriteQueue::spawn(store.clone(), events.clone(), WRITE_QUEUE_CAPACITY);
+        AppState {
+            store,
+            events,
+            queue,
+            api_key: Arc::from(TEST_API_KEY),
+        }
+    }
+
     fn build_request(method: http::Method, uri: &str, maybe_json: Option<&Value>) -> Request<Body> {
         let body = match maybe_json {
             Some(json) => Body::from(
@@ -235,6 +459,16 @@ mod tests {
             .unwrap()
     }
 
+    fn build_authenticated_request(method: http::Method, uri: &str, maybe_json: Option<&Value>) -> Request<Body> {
+        let mut request = build_request(method, uri, maybe_json);
+        request.headers_mut().insert(
+            http::header::AUTHORIZATION,
+            http::HeaderValue::from_static(TEST_API_KEY),
+        );
+
+        request
+    }
+
     async fn call(request: Request<Body>, app: &mut RouterIntoService<Body>) -> Response<Body> {
         ServiceExt::<Request<Body>>::ready(app)
             .await
@@ -0,0 +1,129 @@
+use std::{fmt, sync::Arc};
+
+use tokio::sync::{broadcast, mpsc, oneshot};
+use uuid::Uuid;
+
+use crate::{
+    store::{PriceStore, StoreError},
+    PriceEvent, TPrice,
+};
+
+enum WriteCommand {
+    Create { id: Uuid, price: TPrice, reply: oneshot::Sender<Result<(), StoreError>> },
+    Update { id: Uuid, price: TPrice, reply: oneshot::Sender<Result<bool, StoreError>> },
+    Delete { id: Uuid, reply: oneshot::Sender<Result<bool, StoreError>> },
+}
+
+/// A write submitted to the queue couldn't be completed.
+#[derive(Debug)]
+pub enum WriteError {
+    /// The worker task that drains the queue is gone, so the reply will
+    /// never arrive. Submitting blocks (providing backpressure) rather
+    /// than failing while the queue is merely full; this only fires once
+    /// the worker itself has stopped.
+    QueueUnavailable,
+    /// The command reached the worker and the store rejected it.
+    Store(StoreError),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::QueueUnavailable => write!(f, "write queue worker is not running"),
+            WriteError::Store(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// Serializes writes through a single worker task that owns the store, so
+/// concurrent mutating requests are applied in submission order instead of
+/// racing each other for the write lock.
+#[derive(Clone)]
+pub struct WriteQueue {
+    sender: mpsc::Sender<WriteCommand>,
+    capacity: usize,
+}
+
+impl WriteQueue {
+    pub fn spawn<S: PriceStore + 'static>(
+        store: Arc<S>,
+        events: broadcast::Sender<PriceEvent>,
+        capacity: usize,
+    ) -> Self {
+        let (sender, mut receiver) = mpsc::channel(capacity);
+
+        tokio::spawn(async move {
+            while let Some(command) = receiver.recv().await {
+                match command {
+                    WriteCommand::Create { id, price, reply } => {
+                        let result = store.create(id, price).await;
+                        if result.is_ok() {
+                            let _ = events.send(PriceEvent::Created { id, price });
+                        }
+                        let _ = reply.send(result);
+                    }
+                    WriteCommand::Update { id, price, reply } => {
+                        let result = store.update(id, price).await;
+                        if let Ok(true) = result {
+                            let _ = events.send(PriceEvent::Updated { id, price });
+                        }
+                        let _ = reply.send(result);
+                    }
+                    WriteCommand::Delete { id, reply } => {
+                        let result = store.delete(id).await;
+                        if let Ok(true) = result {
+                            let _ = events.send(PriceEvent::Deleted { id });
+                        }
+                        let _ = reply.send(result);
+                    }
+                }
+            }
+        });
+
+        Self { sender, capacity }
+    }
+
+    /// Writes currently queued ahead of the worker, so operators can watch
+    /// for backpressure building up.
+    pub fn depth(&self) -> usize {
+        self.capacity - self.sender.capacity()
+    }
+
+    pub async fn create(&self, id: Uuid, price: TPrice) -> Result<(), WriteError> {
+        let (reply, receiver) = oneshot::channel();
+        self.sender
+            .send(WriteCommand::Create { id, price, reply })
+            .await
+            .map_err(|_| WriteError::QueueUnavailable)?;
+        receiver
+            .await
+            .map_err(|_| WriteError::QueueUnavailable)?
+            .map_err(WriteError::Store)
+    }
+
+    pub async fn update(&self, id: Uuid, price: TPrice) -> Result<bool, WriteError> {
+        let (reply, receiver) = oneshot::channel();
+        self.sender
+            .send(WriteCommand::Update { id, price, reply })
+            .await
+            .map_err(|_| WriteError::QueueUnavailable)?;
+        receiver
+            .await
+            .map_err(|_| WriteError::QueueUnavailable)?
+            .map_err(WriteError::Store)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<bool, WriteError> {
+        let (reply, receiver) = oneshot::channel();
+        self.sender
+            .send(WriteCommand::Delete { id, reply })
+            .await
+            .map_err(|_| WriteError::QueueUnavailable)?;
+        receiver
+            .await
+            .map_err(|_| WriteError::QueueUnavailable)?
+            .map_err(WriteError::Store)
+    }
+}
@@ -0,0 +1,191 @@
+use std::{collections::HashMap, fmt};
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::TPrice;
+
+/// A write couldn't be applied to the backing store itself (disk full,
+/// corrupted database, permission error, ...), as opposed to a store
+/// operation that completed but found no matching id.
+#[derive(Debug)]
+pub struct StoreError(String);
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "price store error: {}", self.0)
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<sled::Error> for StoreError {
+    fn from(err: sled::Error) -> Self {
+        StoreError(err.to_string())
+    }
+}
+
+/// Storage backend for prices, abstracted so handlers don't care whether
+/// data lives in memory or on disk.
+#[async_trait]
+pub trait PriceStore: Send + Sync {
+    async fn list(&self) -> Vec<TPrice>;
+    async fn get(&self, id: Uuid) -> Option<TPrice>;
+    async fn create(&self, id: Uuid, price: TPrice) -> Result<(), StoreError>;
+    async fn update(&self, id: Uuid, price: TPrice) -> Result<bool, StoreError>;
+    async fn delete(&self, id: Uuid) -> Result<bool, StoreError>;
+}
+
+/// Non-persistent store backed by a `HashMap` behind a `RwLock`. Data is
+/// lost on restart; use `SledStore` when that matters.
+#[derive(Default)]
+pub struct MemoryStore {
+    prices: RwLock<HashMap<Uuid, TPrice>>,
+}
+
+impl MemoryStore {
+    pub fn from_map(prices: HashMap<Uuid, TPrice>) -> Self {
+        Self { prices: RwLock::new(prices) }
+    }
+}
+
+#[async_trait]
+impl PriceStore for MemoryStore {
+    async fn list(&self) -> Vec<TPrice> {
+        self.prices.read().await.values().cloned().collect()
+    }
+
+    async fn get(&self, id: Uuid) -> Option<TPrice> {
+        self.prices.read().await.get(&id).cloned()
+    }
+
+    async fn create(&self, id: Uuid, price: TPrice) -> Result<(), StoreError> {
+        self.prices.write().await.insert(id, price);
+        Ok(())
+    }
+
+    async fn update(&self, id: Uuid, price: TPrice) -> Result<bool, StoreError> {
+        match self.prices.write().await.get_mut(&id) {
+            Some(old_price) => {
+                *old_price = price;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, StoreError> {
+        Ok(self.prices.write().await.remove(&id).is_some())
+    }
+}
+
+/// Persistent store backed by an embedded `sled` database, so prices
+/// survive process restarts.
+pub struct SledStore {
+    db: sled::Db,
+}
+
+impl SledStore {
+    pub fn open(path: impl AsRef<std::path::Path>) -> sled::Result<Self> {
+        Ok(Self { db: sled::open(path)? })
+    }
+}
+
+#[async_trait]
+impl PriceStore for SledStore {
+    async fn list(&self) -> Vec<TPrice> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db.iter()
+                .values()
+                .filter_map(|value| value.ok())
+                .map(|value| decode_price(&value))
+                .collect()
+        })
+        .await
+        .expect("sled list task panicked")
+    }
+
+    async fn get(&self, id: Uuid) -> Option<TPrice> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db.get(id.as_bytes()).ok().flatten().map(|value| decode_price(&value))
+        })
+        .await
+        .expect("sled get task panicked")
+    }
+
+    async fn create(&self, id: Uuid, price: TPrice) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || -> sled::Result<()> {
+            db.insert(id.as_bytes(), &price.to_be_bytes())?;
+            Ok(())
+        })
+        .await
+        .expect("sled create task panicked")
+        .map_err(StoreError::from)
+    }
+
+    async fn update(&self, id: Uuid, price: TPrice) -> Result<bool, StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || -> sled::Result<bool> {
+            if db.contains_key(id.as_bytes())? {
+                db.insert(id.as_bytes(), &price.to_be_bytes())?;
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        })
+        .await
+        .expect("sled update task panicked")
+        .map_err(StoreError::from)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || -> sled::Result<bool> { Ok(db.remove(id.as_bytes())?.is_some()) })
+            .await
+            .expect("sled delete task panicked")
+            .map_err(StoreError::from)
+    }
+}
+
+fn decode_price(bytes: &sled::IVec) -> TPrice {
+    TPrice::from_be_bytes(bytes.as_ref().try_into().expect("stored price is always 8 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sled_store_round_trips_a_price_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        let id = Uuid::new_v4();
+
+        assert_eq!(store.get(id).await, None);
+
+        store.create(id, 355).await.unwrap();
+        assert_eq!(store.get(id).await, Some(355));
+        assert_eq!(store.list().await, vec![355]);
+
+        assert!(store.update(id, 235).await.unwrap());
+        assert_eq!(store.get(id).await, Some(235));
+
+        assert!(store.delete(id).await.unwrap());
+        assert_eq!(store.get(id).await, None);
+        assert_eq!(store.list().await, Vec::<TPrice>::new());
+    }
+
+    #[tokio::test]
+    async fn sled_store_update_and_delete_missing_id_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        let id = Uuid::new_v4();
+
+        assert!(!store.update(id, 100).await.unwrap());
+        assert!(!store.delete(id).await.unwrap());
+    }
+}